@@ -1,21 +1,88 @@
 use serde::{Serialize, Deserialize};
-use std::{hash::{Hash, Hasher}, f64::consts::LN_2};
+use std::{fmt, hash::{Hash, Hasher}, f64::consts::LN_2};
 use twox_hash::XxHash;
 
+/// Errors returned by fallible `BloomBox` operations.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BloomBoxError {
+    /// The two filters have differing `size`, `seeds`, or `k` and cannot be
+    /// combined.
+    IncompatibleFilters,
+}
+
+impl fmt::Display for BloomBoxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BloomBoxError::IncompatibleFilters => {
+                write!(f, "cannot combine filters with differing size, seeds, or hash count")
+            }
+        }
+    }
+}
+
+impl std::error::Error for BloomBoxError {}
+
+/// A specialized `Result` for `BloomBox` operations.
+pub type Result<T> = std::result::Result<T, BloomBoxError>;
+
+/// A packed bit store backed by `Vec<u64>`, using one bit per entry instead of
+/// one byte. Bit `i` lives in word `i >> 6` at position `i & 63`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct PackedBits {
+    words: Vec<u64>,
+}
+
+impl PackedBits {
+    fn new(size: usize) -> PackedBits {
+        PackedBits {
+            words: vec![0; size.div_ceil(64)],
+        }
+    }
+
+    /// Sets bit `i` and returns `true` if it flipped from `0` to `1`.
+    fn set(&mut self, i: usize) -> bool {
+        let word = &mut self.words[i >> 6];
+        let mask = 1 << (i & 63);
+        let was_set = *word & mask != 0;
+        *word |= mask;
+        !was_set
+    }
+
+    fn get(&self, i: usize) -> bool {
+        self.words[i >> 6] & (1 << (i & 63)) != 0
+    }
+
+    fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+}
+
+/// # Serialized representation
+///
+/// The bit store is serialized as the packed `Vec<u64>` word vector (field
+/// `bits`), not as the old bool-per-entry `bit_vector` array. This shrinks the
+/// on-disk footprint roughly 8x but is a breaking change: JSON produced by an
+/// earlier `BloomBox` that serialized `bit_vector` cannot be deserialized into
+/// this version and must be rebuilt from its source items.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct BloomBox {
-    bit_vector: Vec<bool>,
+    bits: PackedBits,
     seeds: Vec<u64>,
     size: usize,
+    k: usize,
+    num_bits_set: usize,
     pub insert_count: usize,
 }
 
 impl BloomBox {
     pub fn new(size: usize, seeds: Vec<u64>) -> BloomBox {
+        let k = seeds.len();
         BloomBox {
-            bit_vector: vec![false; size],
+            bits: PackedBits::new(size),
             seeds,
             size,
+            k,
+            num_bits_set: 0,
             insert_count: 0,
         }
     }
@@ -34,6 +101,212 @@ impl BloomBox {
     ///
     /// A new `BloomBox` optimized for the provided false positive rate and expected number of items.
     pub fn with_rate(false_positive_rate: f64, expected_num_items: usize) -> BloomBox {
+        let ln_p = false_positive_rate.ln();
+        let n = expected_num_items as f64;
+        let m = (-n * ln_p / LN_2.powi(2)).ceil() as usize;
+        let k = (m as f64 / n * LN_2).ceil() as usize;
+
+        // Only two base hashes are ever evaluated; the remaining k indices are
+        // derived analytically via double hashing, so k is tracked separately.
+        let seeds = vec![0, 1];
+
+        BloomBox {
+            bits: PackedBits::new(m),
+            seeds,
+            size: m,
+            k,
+            num_bits_set: 0,
+            insert_count: 0,
+        }
+    }
+
+    /// Computes the two base hashes `h1` and `h2` of `item` used to derive all
+    /// `k` indices via Kirsch–Mitzenmacher double hashing.
+    fn base_hashes<T: Hash>(&self, item: &T) -> (u64, u64) {
+        let hash_with = |seed: u64| {
+            let mut hasher = XxHash::with_seed(seed);
+            item.hash(&mut hasher);
+            hasher.finish()
+        };
+        // Fall back to derived base seeds if the caller supplied fewer than two
+        // (including none), so a filter built with an empty seed list still
+        // hashes without panicking.
+        let first_seed = self.seeds.first().copied().unwrap_or(0);
+        let second_seed = self
+            .seeds
+            .get(1)
+            .copied()
+            .unwrap_or_else(|| first_seed.wrapping_add(0x9E37_79B9_7F4A_7C15));
+        let h1 = hash_with(first_seed);
+        let h2 = hash_with(second_seed);
+        (h1, h2)
+    }
+
+    /// Derives the `i`th index from the two base hashes: `(h1 + i * h2) % size`.
+    fn index_for(&self, h1: u64, h2: u64, i: usize) -> usize {
+        h1.wrapping_add((i as u64).wrapping_mul(h2)) as usize % self.size
+    }
+
+    pub fn insert<T: Hash>(&mut self, item: &T) {
+        let (h1, h2) = self.base_hashes(item);
+        for i in 0..self.k {
+            let hashed = self.index_for(h1, h2, i);
+            if self.bits.set(hashed) {
+                self.num_bits_set += 1;
+            }
+        }
+        self.insert_count += 1;
+    }
+
+    pub fn contains<T: Hash>(&self, item: &T) -> bool {
+        let (h1, h2) = self.base_hashes(item);
+        for i in 0..self.k {
+            let hashed = self.index_for(h1, h2, i);
+            if !self.bits.get(hashed) {
+                return false;
+            }
+        }
+        true
+    }
+
+    pub fn get_insert_count(&self) -> usize {
+        self.insert_count
+    }
+
+    pub fn get_size(&self) -> usize {
+        self.size
+    }
+
+    pub fn get_seeds(&self) -> &Vec<u64> {
+        &self.seeds
+    }
+
+    pub fn get_num_seeds(&self) -> usize {
+        self.seeds.len()
+    }
+
+    /// Returns the number of hash indices `k` probed per item. `new` sets it to
+    /// the number of seeds given; `with_rate` computes the optimal `k`
+    /// independently of the two base seeds it stores.
+    pub fn get_num_hashes(&self) -> usize {
+        self.k
+    }
+
+    /// Returns the number of bits currently set to `1`.
+    ///
+    /// Unlike `insert_count`, this counts distinct set bits (duplicates and
+    /// hash collisions do not inflate it), which is what the cardinality and
+    /// false-positive-rate estimates are derived from.
+    pub fn get_num_bits_set(&self) -> usize {
+        self.num_bits_set
+    }
+
+    /// Estimates the number of distinct items inserted into the filter.
+    ///
+    /// Uses the Swamidass–Baldi estimator `n* = -(m / k) * ln(1 - X / m)`,
+    /// where `m` is the filter size, `k` the number of hash indices, and `X`
+    /// the number of set bits. Unlike `insert_count`, this ignores duplicate
+    /// inserts and accounts for saturation, so it is a better signal of how
+    /// full the filter actually is.
+    pub fn estimated_item_count(&self) -> f64 {
+        let m = self.size as f64;
+        let k = self.k as f64;
+        let x = self.num_bits_set as f64;
+        -(m / k) * (1.0 - x / m).ln()
+    }
+
+    /// Returns the live false positive rate `(X / m)^k` implied by the current
+    /// fill level, letting users detect when a fixed-size filter has degraded
+    /// past its design point.
+    pub fn current_false_positive_rate(&self) -> f64 {
+        let m = self.size as f64;
+        let x = self.num_bits_set as f64;
+        (x / m).powi(self.k as i32)
+    }
+
+    /// Returns an error unless `other` has the same `size`, `seeds`, and `k`,
+    /// which is required for the bit vectors to line up slot-for-slot.
+    fn check_compatible(&self, other: &BloomBox) -> Result<()> {
+        if self.size != other.size || self.seeds != other.seeds || self.k != other.k {
+            return Err(BloomBoxError::IncompatibleFilters);
+        }
+        Ok(())
+    }
+
+    /// Returns the union of two compatible filters by OR-ing their bit vectors.
+    ///
+    /// Merging filters built in parallel across shards is a common pattern. The
+    /// resulting `insert_count` is the sum of the inputs and is therefore an
+    /// upper bound, since items present in both filters are double-counted.
+    pub fn union(&self, other: &BloomBox) -> Result<BloomBox> {
+        self.check_compatible(other)?;
+        let mut result = self.clone();
+        for (word, other_word) in result.bits.words.iter_mut().zip(&other.bits.words) {
+            *word |= *other_word;
+        }
+        result.num_bits_set = result.bits.count_ones();
+        result.insert_count = self.insert_count + other.insert_count;
+        Ok(result)
+    }
+
+    /// Returns the intersection of two compatible filters by AND-ing their bit
+    /// vectors, approximating set-intersection membership.
+    ///
+    /// Intersection cannot preserve an exact item count, so `insert_count` on
+    /// the result is reset to `0`.
+    pub fn intersect(&self, other: &BloomBox) -> Result<BloomBox> {
+        self.check_compatible(other)?;
+        let mut result = self.clone();
+        for (word, other_word) in result.bits.words.iter_mut().zip(&other.bits.words) {
+            *word &= *other_word;
+        }
+        result.num_bits_set = result.bits.count_ones();
+        result.insert_count = 0;
+        Ok(result)
+    }
+}
+
+/// A counting variant of [`BloomBox`] backed by 8-bit counters instead of a bit
+/// vector.
+///
+/// Because each slot is a counter rather than a single bit, a `CountingBloomBox`
+/// can tell "set once" apart from "set many times" and therefore supports
+/// [`remove`](CountingBloomBox::remove). This mirrors the counting Bloom design
+/// used by Servo's selector filter and trades roughly 8x the memory of
+/// [`BloomBox`] for deletion support, so users that do not need `remove` can
+/// keep paying nothing by using [`BloomBox`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CountingBloomBox {
+    counters: Vec<u8>,
+    seeds: Vec<u64>,
+    size: usize,
+    pub insert_count: usize,
+}
+
+impl CountingBloomBox {
+    pub fn new(size: usize, seeds: Vec<u64>) -> CountingBloomBox {
+        CountingBloomBox {
+            counters: vec![0; size],
+            seeds,
+            size,
+            insert_count: 0,
+        }
+    }
+
+    /// Creates a new `CountingBloomBox` based on the desired false positive rate and expected number of items.
+    ///
+    /// The size of the counter array and the number of hash functions are automatically calculated
+    /// to be optimal for the provided false positive rate and expected number of items.
+    ///
+    /// # Arguments
+    ///
+    /// * `false_positive_rate` - The desired false positive rate (e.g., 0.01 for 1%)
+    /// * `expected_num_items` - The expected number of items to be inserted into the CountingBloomBox
+    ///
+    /// # Returns
+    ///
+    /// A new `CountingBloomBox` optimized for the provided false positive rate and expected number of items.
+    pub fn with_rate(false_positive_rate: f64, expected_num_items: usize) -> CountingBloomBox {
         let ln_p = false_positive_rate.ln();
         let n = expected_num_items as f64;
         let m = (-n * ln_p / LN_2.powi(2)).ceil() as usize;
@@ -42,8 +315,8 @@ impl BloomBox {
         // Generate seeds for the hash functions
         let seeds = (0..k).collect();
 
-        BloomBox {
-            bit_vector: vec![false; m],
+        CountingBloomBox {
+            counters: vec![0; m],
             seeds,
             size: m,
             insert_count: 0,
@@ -59,15 +332,42 @@ impl BloomBox {
     pub fn insert<T: Hash>(&mut self, item: &T) {
         for &seed in &self.seeds {
             let hashed = self.hash_item(item, seed);
-            self.bit_vector[hashed] = true;
+            // Saturate at 255 and treat it as a sticky max so the counter never
+            // wraps around and corrupts membership.
+            self.counters[hashed] = self.counters[hashed].saturating_add(1);
         }
         self.insert_count += 1;
     }
 
+    /// Removes a previously-inserted item by decrementing each of its `k`
+    /// counters.
+    ///
+    /// `remove` is only defined for items that were actually inserted: removing
+    /// an item the filter does not contain is a no-op, since decrementing its
+    /// counters could corrupt the counts of genuinely-present members. Counters
+    /// saturate at 0 and a counter pinned at 255 is treated as a sticky max (it
+    /// is never decremented) so that a previously saturated slot cannot
+    /// underflow and wrongly report an item as absent.
+    pub fn remove<T: Hash>(&mut self, item: &T) {
+        if !self.contains(item) {
+            return;
+        }
+        for &seed in &self.seeds {
+            let hashed = self.hash_item(item, seed);
+            let counter = &mut self.counters[hashed];
+            if *counter != 0 && *counter != u8::MAX {
+                *counter -= 1;
+            }
+        }
+        if self.insert_count > 0 {
+            self.insert_count -= 1;
+        }
+    }
+
     pub fn contains<T: Hash>(&self, item: &T) -> bool {
         for &seed in &self.seeds {
             let hashed = self.hash_item(item, seed);
-            if !self.bit_vector[hashed] {
+            if self.counters[hashed] == 0 {
                 return false;
             }
         }
@@ -92,6 +392,104 @@ impl BloomBox {
 }
 
 
+/// Default geometric growth factor applied to each new layer's capacity.
+const SCALABLE_GROWTH_FACTOR: usize = 2;
+
+/// Per-layer tightening ratio applied to the target false positive rate so that
+/// the compounded rate across all layers stays under the user's bound.
+const SCALABLE_TIGHTENING_RATIO: f64 = 0.8;
+
+/// A growable filter that preserves a target false positive rate as it fills.
+///
+/// A single [`BloomBox`] sized for `expected_num_items` silently blows past its
+/// target rate once more items are inserted. `ScalableBloomBox` instead keeps a
+/// stack of inner [`BloomBox`] layers: when the newest layer reaches its
+/// capacity a larger layer is allocated (geometric growth, `2x` by default)
+/// with a tighter per-layer target of `rate * (1 - 0.8) * 0.8^level`, so the
+/// per-layer targets sum to at most `rate` and the compounded rate stays under
+/// the user's bound. Inserts always write to the newest layer
+/// and lookups succeed if any layer reports membership.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ScalableBloomBox {
+    layers: Vec<BloomBox>,
+    capacities: Vec<usize>,
+    initial_capacity: usize,
+    growth_factor: usize,
+    target_rate: f64,
+}
+
+impl ScalableBloomBox {
+    /// Creates a new `ScalableBloomBox` with a single layer sized for
+    /// `initial_capacity` items at the given target false positive rate.
+    ///
+    /// # Arguments
+    ///
+    /// * `false_positive_rate` - The target false positive rate to stay under (e.g., 0.01 for 1%)
+    /// * `initial_capacity` - The capacity of the first layer before it grows
+    ///
+    /// # Returns
+    ///
+    /// A new `ScalableBloomBox` that grows to preserve the target rate.
+    pub fn new(false_positive_rate: f64, initial_capacity: usize) -> ScalableBloomBox {
+        let mut bloom_box = ScalableBloomBox {
+            layers: Vec::new(),
+            capacities: Vec::new(),
+            initial_capacity,
+            growth_factor: SCALABLE_GROWTH_FACTOR,
+            target_rate: false_positive_rate,
+        };
+        bloom_box.grow();
+        bloom_box
+    }
+
+    /// Allocates a new, larger layer with a tighter per-layer target rate.
+    fn grow(&mut self) {
+        let level = self.layers.len();
+        let capacity = self.initial_capacity * self.growth_factor.pow(level as u32);
+        let rate = self.target_rate
+            * (1.0 - SCALABLE_TIGHTENING_RATIO)
+            * SCALABLE_TIGHTENING_RATIO.powi(level as i32);
+        self.layers.push(BloomBox::with_rate(rate, capacity));
+        self.capacities.push(capacity);
+    }
+
+    pub fn insert<T: Hash>(&mut self, item: &T) {
+        let current = self.layers.len() - 1;
+        if self.layers[current].insert_count >= self.capacities[current] {
+            self.grow();
+        }
+        self.layers.last_mut().unwrap().insert(item);
+    }
+
+    pub fn contains<T: Hash>(&self, item: &T) -> bool {
+        self.layers.iter().any(|layer| layer.contains(item))
+    }
+
+    /// Returns the total number of items inserted across all layers.
+    pub fn item_count(&self) -> usize {
+        self.layers.iter().map(|layer| layer.insert_count).sum()
+    }
+
+    /// Returns the aggregate estimated false positive rate across all layers.
+    ///
+    /// This is the compounded bound `1 - prod(1 - r_i)`, where `r_i` is the
+    /// tightened target rate of layer `i`.
+    pub fn estimated_false_positive_rate(&self) -> f64 {
+        let survival: f64 = (0..self.layers.len())
+            .map(|level| {
+                1.0 - self.target_rate
+                    * (1.0 - SCALABLE_TIGHTENING_RATIO)
+                    * SCALABLE_TIGHTENING_RATIO.powi(level as i32)
+            })
+            .product();
+        1.0 - survival
+    }
+
+    pub fn get_num_layers(&self) -> usize {
+        self.layers.len()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -154,4 +552,98 @@ mod tests {
         let false_positive_rate = false_positives.len() as f64 / checked_items.len() as f64;
         assert!((false_positive_rate - 0.01).abs() < 0.5);
     }
+
+    #[test]
+    fn test_counting_insert_remove() {
+        let seeds = vec![1, 2, 3, 4, 5];
+        let size = 1000;
+        let mut bloom_box = CountingBloomBox::new(size, seeds);
+
+        bloom_box.insert(&"test");
+        assert!(bloom_box.contains(&"test"));
+
+        bloom_box.remove(&"test");
+        assert!(!bloom_box.contains(&"test"));
+    }
+
+    #[test]
+    fn test_counting_duplicate_insert() {
+        let seeds = vec![1, 2, 3, 4, 5];
+        let size = 1000;
+        let mut bloom_box = CountingBloomBox::new(size, seeds);
+
+        // Inserting twice and removing once should still report membership.
+        bloom_box.insert(&"test");
+        bloom_box.insert(&"test");
+        bloom_box.remove(&"test");
+        assert!(bloom_box.contains(&"test"));
+    }
+
+    #[test]
+    fn test_scalable_grows_and_contains() {
+        let mut bloom_box = ScalableBloomBox::new(0.01, 100);
+        assert_eq!(bloom_box.get_num_layers(), 1);
+
+        let items: Vec<_> = (0..1000).map(|i| i.to_string()).collect();
+        for item in &items {
+            bloom_box.insert(item);
+        }
+
+        // The filter should have grown past its initial single layer.
+        assert!(bloom_box.get_num_layers() > 1);
+        assert_eq!(bloom_box.item_count(), items.len());
+
+        // Every inserted item must still be reported as present.
+        for item in &items {
+            assert!(bloom_box.contains(item));
+        }
+
+        // The aggregate estimate stays under the requested bound.
+        assert!(bloom_box.estimated_false_positive_rate() < 0.01);
+    }
+
+    #[test]
+    fn test_union_intersect() {
+        let seeds = vec![1, 2, 3, 4, 5];
+        let size = 1000;
+        let mut a = BloomBox::new(size, seeds.clone());
+        let mut b = BloomBox::new(size, seeds);
+        a.insert(&"a");
+        a.insert(&"shared");
+        b.insert(&"b");
+        b.insert(&"shared");
+
+        let union = a.union(&b).unwrap();
+        assert!(union.contains(&"a"));
+        assert!(union.contains(&"b"));
+        assert!(union.contains(&"shared"));
+
+        let intersect = a.intersect(&b).unwrap();
+        assert!(intersect.contains(&"shared"));
+    }
+
+    #[test]
+    fn test_incompatible_union() {
+        let a = BloomBox::new(1000, vec![1, 2, 3]);
+        let b = BloomBox::new(500, vec![1, 2, 3]);
+        assert!(matches!(a.union(&b), Err(BloomBoxError::IncompatibleFilters)));
+    }
+
+    #[test]
+    fn test_cardinality_estimation() {
+        let mut bloom_box = BloomBox::with_rate(0.01, 10000);
+
+        let items: Vec<_> = (0..5000).map(|i| i.to_string()).collect();
+        for item in &items {
+            bloom_box.insert(item);
+        }
+
+        // The cardinality estimate should be within 5% of the true count.
+        let estimate = bloom_box.estimated_item_count();
+        assert!((estimate - 5000.0).abs() / 5000.0 < 0.05);
+
+        // The live false positive rate stays well under the design rate since
+        // the filter is only half full.
+        assert!(bloom_box.current_false_positive_rate() < 0.01);
+    }
 }